@@ -55,7 +55,7 @@ use sp_runtime::{
 use sp_std::{cmp, collections::btree_map::BTreeMap, prelude::*};
 
 mod migration;
-mod relay_state_snapshot;
+pub mod relay_state_snapshot;
 #[macro_use]
 pub mod validate_block;
 #[cfg(test)]
@@ -106,6 +106,10 @@ pub mod pallet {
 		/// Something which can be notified when the validation data is set.
 		type OnValidationData: OnValidationData;
 
+		/// Something which can read from the relay chain state proof when the validation data is
+		/// set.
+		type OnRelayChainStateProof: OnRelayChainStateProof;
+
 		/// Returns the parachain ID we are running with.
 		type SelfParaId: Get<ParaId>;
 
@@ -127,6 +131,20 @@ pub mod pallet {
 
 		/// The weight we reserve at the beginning of the block for processing XCMP messages.
 		type ReservedXcmpWeight: Get<Weight>;
+
+		/// The maximum number of blocks in a row that may be built on a relay parent after the
+		/// first block that was built on it.
+		///
+		/// Only one of these blocks can get included by the relay chain, so continuing to build
+		/// on a relay parent that doesn't advance only grows an unincludable fork. Once the limit
+		/// is reached [`Event::RelayParentStale`] is emitted and building another block on the
+		/// same relay parent panics. With a limit of zero the event is emitted for every block,
+		/// as each block is the last one allowed on its relay parent. `None` disables the check.
+		type MaxStaleRelayParentBlocks: Get<Option<u32>>;
+
+		/// What to do with a block that is built on a relay parent with a lower block number than
+		/// the relay parent of the previous block.
+		type RelayParentRegressionPolicy: Get<RelayParentRegressionPolicy>;
 	}
 
 	#[pallet::hooks]
@@ -304,6 +322,8 @@ pub mod pallet {
 			} = data;
 
 			Self::validate_validation_data(&vfp);
+			let relay_parent_progress_weight =
+				Self::note_relay_parent_progress(vfp.relay_parent_number);
 
 			let relay_state_proof = RelayChainStateProof::new(
 				T::SelfParaId::get(),
@@ -355,7 +375,9 @@ pub mod pallet {
 			<T::OnValidationData as OnValidationData>::on_validation_data(&vfp);
 
 			// TODO: This is more than zero, but will need benchmarking to figure out what.
-			let mut total_weight = 0;
+			let mut total_weight = relay_parent_progress_weight;
+			total_weight +=
+				T::OnRelayChainStateProof::on_relay_chain_state_proof(&relay_state_proof);
 			total_weight += Self::process_inbound_downward_messages(
 				relevant_messaging_state.dmq_mqc_head,
 				downward_messages,
@@ -418,6 +440,13 @@ pub mod pallet {
 		/// Downward messages were processed using the given weight.
 		/// \[ weight_used, result_mqc_head \]
 		DownwardMessagesProcessed(Weight, relay_chain::Hash),
+		/// The maximum number of blocks were built on the same relay parent. The next block needs
+		/// to be built on a newer relay parent.
+		/// \[ relay_parent_number \]
+		RelayParentStale(RelayChainBlockNumber),
+		/// The relay parent number is lower than the one of the previous block.
+		/// \[ last_relay_parent_number, relay_parent_number \]
+		RelayParentNumberRegressed(RelayChainBlockNumber, RelayChainBlockNumber),
 	}
 
 	#[pallet::error]
@@ -567,6 +596,28 @@ pub mod pallet {
 	#[pallet::storage]
 	pub(super) type AuthorizedUpgrade<T: Config> = StorageValue<_, T::Hash>;
 
+	/// The relay chain block number of the relay parent the last block was built on.
+	///
+	/// In contrast to [`ValidationData`] this value carries over to the next block.
+	#[pallet::storage]
+	pub(super) type LastRelayChainBlockNumber<T: Config> = StorageValue<_, RelayChainBlockNumber>;
+
+	/// The highest relay chain block number of all relay parents blocks were built on so far.
+	///
+	/// This is what [`RelaychainBlockNumberProvider`] returns with
+	/// [`RelayParentRegressionPolicy::Clamp`]. It is only tracked while that policy is configured.
+	#[pallet::storage]
+	pub(super) type HighestRelayChainBlockNumber<T: Config> =
+		StorageValue<_, RelayChainBlockNumber>;
+
+	/// The number of blocks in a row that were built on the relay parent of the last block, not
+	/// counting the first block that was built on it.
+	///
+	/// This is reset to zero as soon as a block is built on a different relay parent.
+	#[pallet::storage]
+	#[pallet::getter(fn stale_relay_parent_blocks)]
+	pub(super) type StaleRelayParentBlocks<T: Config> = StorageValue<_, u32, ValueQuery>;
+
 	#[pallet::inherent]
 	impl<T: Config> ProvideInherent for Pallet<T> {
 		type Call = Call<T>;
@@ -709,6 +760,64 @@ impl<T: Config> Pallet<T> {
 		});
 	}
 
+	/// Keep track of the number of blocks in a row that were built on the same relay parent.
+	///
+	/// **Panics** if more than [`Config::MaxStaleRelayParentBlocks`] blocks in a row were built on
+	///            the given relay parent.
+	///
+	/// Also applies the [`Config::RelayParentRegressionPolicy`] in case the relay parent number
+	/// went backwards, e.g. because of a relay chain reorg.
+	///
+	/// **Panics** if the relay parent number went backwards and the policy is
+	///            [`RelayParentRegressionPolicy::Reject`].
+	///
+	/// Returns the weight consumed.
+	fn note_relay_parent_progress(relay_parent_number: RelayChainBlockNumber) -> Weight {
+		let policy = T::RelayParentRegressionPolicy::get();
+		let last_relay_parent_number = <LastRelayChainBlockNumber<T>>::get();
+		let mut weight = T::DbWeight::get().reads(1);
+
+		if let Some(last) = last_relay_parent_number.filter(|last| *last > relay_parent_number) {
+			if policy == RelayParentRegressionPolicy::Reject {
+				panic!("Relay parent number is lower than the one of the previous block");
+			}
+			Self::deposit_event(Event::RelayParentNumberRegressed(last, relay_parent_number));
+		}
+
+		// Staleness is about the actual relay parent, a fork that advances after a reorg isn't
+		// stale even if it is still below the highest relay parent number seen.
+		let stale_blocks = match last_relay_parent_number {
+			Some(last) if last == relay_parent_number => {
+				weight += T::DbWeight::get().reads(1);
+				<StaleRelayParentBlocks<T>>::get().saturating_add(1)
+			},
+			_ => 0,
+		};
+
+		if let Some(max_stale_blocks) = T::MaxStaleRelayParentBlocks::get() {
+			assert!(
+				stale_blocks <= max_stale_blocks,
+				"Relay parent didn't advance for more than the allowed number of blocks",
+			);
+			if stale_blocks == max_stale_blocks {
+				Self::deposit_event(Event::RelayParentStale(relay_parent_number));
+			}
+		}
+
+		<LastRelayChainBlockNumber<T>>::put(relay_parent_number);
+		<StaleRelayParentBlocks<T>>::put(stale_blocks);
+		weight += T::DbWeight::get().writes(2);
+
+		if policy == RelayParentRegressionPolicy::Clamp {
+			<HighestRelayChainBlockNumber<T>>::mutate(|highest| {
+				*highest = Some(highest.map_or(relay_parent_number, |h| h.max(relay_parent_number)))
+			});
+			weight += T::DbWeight::get().reads_writes(1, 1);
+		}
+
+		weight
+	}
+
 	/// Process all inbound downward messages relayed by the collator.
 	///
 	/// Checks if the sequence of the messages is valid, dispatches them and communicates the
@@ -996,6 +1105,25 @@ impl<T: Config> UpwardMessageSender for Pallet<T> {
 	}
 }
 
+/// Something that can read from the relay chain state proof of the current block.
+///
+/// The proof is only available while the validation data inherent is executed and isn't kept in
+/// storage. Implementations should read the entries they need, see
+/// [`RelayChainStateProof::read_entry`], and store only the decoded values.
+pub trait OnRelayChainStateProof {
+	/// Called by the validation data inherent with the relay chain state proof of the relay
+	/// parent.
+	///
+	/// Returns the weight consumed.
+	fn on_relay_chain_state_proof(relay_state_proof: &RelayChainStateProof) -> Weight;
+}
+
+impl OnRelayChainStateProof for () {
+	fn on_relay_chain_state_proof(_: &RelayChainStateProof) -> Weight {
+		0
+	}
+}
+
 /// Something that can check the inherents of a block.
 pub trait CheckInherents<Block: BlockT> {
 	/// Check all inherents of the block.
@@ -1008,8 +1136,35 @@ pub trait CheckInherents<Block: BlockT> {
 	) -> frame_support::inherent::CheckInherentsResult;
 }
 
+/// What to do with a block that is built on a relay parent with a lower block number than the
+/// relay parent of the previous block.
+///
+/// This happens when the relay chain reorgs onto a shorter fork. Everything derived from the relay
+/// parent number, like the block number returned by [`RelaychainBlockNumberProvider`], would go
+/// backwards as well.
+#[derive(Clone, Copy, PartialEq, Eq, Default, sp_runtime::RuntimeDebug)]
+pub enum RelayParentRegressionPolicy {
+	/// Accept the block and emit [`Event::RelayParentNumberRegressed`].
+	#[default]
+	AllowWithEvent,
+	/// Accept the block and emit [`Event::RelayParentNumberRegressed`], but keep
+	/// [`RelaychainBlockNumberProvider`] at the highest relay parent number seen since this policy
+	/// was configured.
+	///
+	/// Only [`RelaychainBlockNumberProvider`] returns the clamped number. Everything this pallet
+	/// communicates to or checks against the relay chain keeps using the actual relay parent
+	/// number, as the relay chain validates against it: the validation data, the HRMP watermark,
+	/// the block number in [`Event::ValidationFunctionApplied`] and the stale relay parent
+	/// tracking of [`Config::MaxStaleRelayParentBlocks`].
+	Clamp,
+	/// Reject the block.
+	Reject,
+}
+
 /// Implements [`BlockNumberProvider`] that returns relaychain block number fetched from
 /// validation data.
+///
+/// With [`RelayParentRegressionPolicy::Clamp`] the returned block number never goes backwards.
 /// NTOE: When validation data is not available (e.g. within on_initialize), 0 will be returned.
 pub struct RelaychainBlockNumberProvider<T>(sp_std::marker::PhantomData<T>);
 
@@ -1018,7 +1173,12 @@ impl<T: Config> BlockNumberProvider for RelaychainBlockNumberProvider<T> {
 
 	fn current_block_number() -> relay_chain::BlockNumber {
 		Pallet::<T>::validation_data()
-			.map(|d| d.relay_parent_number)
+			.map(|d| match T::RelayParentRegressionPolicy::get() {
+				// The validation data inherent already updated the highest relay parent number.
+				RelayParentRegressionPolicy::Clamp =>
+					<HighestRelayChainBlockNumber<T>>::get().unwrap_or(d.relay_parent_number),
+				_ => d.relay_parent_number,
+			})
 			.unwrap_or_default()
 	}
 }
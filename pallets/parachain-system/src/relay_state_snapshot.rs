@@ -81,6 +81,10 @@ pub enum Error {
 	HrmpEgressChannelIndex(ReadEntryErr),
 	/// The channel identified by the sender and receiver cannot be extracted.
 	HrmpChannel(ParaId, ParaId, ReadEntryErr),
+	/// An entry read with [`RelayChainStateProof::read_entry`] cannot be extracted.
+	ReadEntry(ReadEntryErr),
+	/// An entry read with [`RelayChainStateProof::read_optional_entry`] cannot be extracted.
+	ReadOptionalEntry(ReadEntryErr),
 }
 
 #[derive(Debug)]
@@ -273,4 +277,25 @@ impl RelayChainStateProof {
 		)
 		.map_err(Error::UpgradeRestriction)
 	}
+
+	/// Read an entry given by the key and try to decode it. If the value specified by the key
+	/// according to the proof is empty, the `fallback` value will be returned.
+	///
+	/// This allows reading relay chain storage that has no dedicated accessor, as long as the
+	/// collator put it into the proof.
+	///
+	/// Returns an error if anything failed at reading or decoding, or if the value is empty and no
+	/// fallback was provided.
+	pub fn read_entry<T: Decode>(&self, key: &[u8], fallback: Option<T>) -> Result<T, Error> {
+		read_entry(&self.trie_backend, key, fallback).map_err(Error::ReadEntry)
+	}
+
+	/// Read an optional entry given by the key and try to decode it.
+	///
+	/// Returns `None` if the value specified by the key according to the proof is empty.
+	///
+	/// Returns an error if anything failed at reading or decoding.
+	pub fn read_optional_entry<T: Decode>(&self, key: &[u8]) -> Result<Option<T>, Error> {
+		read_optional_entry(&self.trie_backend, key).map_err(Error::ReadOptionalEntry)
+	}
 }
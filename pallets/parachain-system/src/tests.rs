@@ -96,21 +96,66 @@ impl frame_system::Config for Test {
 impl Config for Test {
 	type Event = Event;
 	type OnValidationData = ();
+	type OnRelayChainStateProof = ReadRelayRandomness;
 	type SelfParaId = ParachainId;
 	type OutboundXcmpMessageSource = FromThreadLocal;
 	type DmpMessageHandler = SaveIntoThreadLocal;
 	type ReservedDmpWeight = ReservedDmpWeight;
 	type XcmpMessageHandler = SaveIntoThreadLocal;
 	type ReservedXcmpWeight = ReservedXcmpWeight;
+	type MaxStaleRelayParentBlocks = MaxStaleRelayParentBlocks;
+	type RelayParentRegressionPolicy = RegressionPolicy;
 }
 
 pub struct FromThreadLocal;
 pub struct SaveIntoThreadLocal;
+pub struct RegressionPolicy;
+pub struct MaxStaleRelayParentBlocks;
+pub struct ReadRelayRandomness;
+
+/// The relay chain storage key [`ReadRelayRandomness`] reads from the relay chain state proof.
+const RANDOMNESS_KEY: &[u8] = b"mock_randomness";
 
 std::thread_local! {
 	static HANDLED_DMP_MESSAGES: RefCell<Vec<(relay_chain::BlockNumber, Vec<u8>)>> = RefCell::new(Vec::new());
 	static HANDLED_XCMP_MESSAGES: RefCell<Vec<(ParaId, relay_chain::BlockNumber, Vec<u8>)>> = RefCell::new(Vec::new());
 	static SENT_MESSAGES: RefCell<Vec<(ParaId, Vec<u8>)>> = RefCell::new(Vec::new());
+	static RELAY_PARENT_REGRESSION_POLICY: RefCell<RelayParentRegressionPolicy> = RefCell::new(Default::default());
+	static MAX_STALE_RELAY_PARENT_BLOCKS: RefCell<Option<u32>> = RefCell::new(Some(1));
+	static RELAY_RANDOMNESS: RefCell<Option<H256>> = RefCell::new(None);
+}
+
+fn set_max_stale_relay_parent_blocks(max: Option<u32>) {
+	MAX_STALE_RELAY_PARENT_BLOCKS.with(|m| *m.borrow_mut() = max);
+}
+
+impl Get<Option<u32>> for MaxStaleRelayParentBlocks {
+	fn get() -> Option<u32> {
+		MAX_STALE_RELAY_PARENT_BLOCKS.with(|m| *m.borrow())
+	}
+}
+
+fn set_relay_parent_regression_policy(policy: RelayParentRegressionPolicy) {
+	RELAY_PARENT_REGRESSION_POLICY.with(|p| *p.borrow_mut() = policy);
+}
+
+impl Get<RelayParentRegressionPolicy> for RegressionPolicy {
+	fn get() -> RelayParentRegressionPolicy {
+		RELAY_PARENT_REGRESSION_POLICY.with(|p| *p.borrow())
+	}
+}
+
+fn relay_randomness() -> Option<H256> {
+	RELAY_RANDOMNESS.with(|r| *r.borrow())
+}
+
+impl OnRelayChainStateProof for ReadRelayRandomness {
+	fn on_relay_chain_state_proof(relay_state_proof: &RelayChainStateProof) -> Weight {
+		// Only the relay chain state proof of some tests contains the randomness.
+		let randomness = relay_state_proof.read_entry(RANDOMNESS_KEY, None).ok();
+		RELAY_RANDOMNESS.with(|r| *r.borrow_mut() = randomness);
+		0
+	}
 }
 
 fn send_message(dest: ParaId, message: Vec<u8>) {
@@ -173,6 +218,7 @@ impl XcmpMessageHandler for SaveIntoThreadLocal {
 fn new_test_ext() -> sp_io::TestExternalities {
 	HANDLED_DMP_MESSAGES.with(|m| m.borrow_mut().clear());
 	HANDLED_XCMP_MESSAGES.with(|m| m.borrow_mut().clear());
+	RELAY_RANDOMNESS.with(|r| *r.borrow_mut() = None);
 
 	frame_system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
 }
@@ -269,7 +315,6 @@ impl BlockTests {
 		self
 	}
 
-	#[allow(dead_code)] // might come in handy in future. If now is future and it still hasn't - feel free.
 	fn with_validation_data<F>(mut self, f: F) -> Self
 	where
 		F: 'static + Fn(&BlockTests, RelayChainBlockNumber, &mut PersistedValidationData),
@@ -892,3 +937,249 @@ fn receive_hrmp_after_pause() {
 			});
 		});
 }
+
+#[test]
+fn stale_relay_parent_is_tracked() {
+	BlockTests::new()
+		.with_validation_data(|_, block_number, data| {
+			// Blocks 1 and 2 are built on the same relay parent.
+			data.relay_parent_number = block_number.max(2);
+		})
+		.add_with_post_test(
+			1,
+			|| {},
+			|| {
+				assert_eq!(ParachainSystem::stale_relay_parent_blocks(), 0);
+			},
+		)
+		.add_with_post_test(
+			2,
+			|| {},
+			|| {
+				assert_eq!(ParachainSystem::stale_relay_parent_blocks(), 1);
+				let stale = crate::Event::<Test>::RelayParentStale(2);
+				assert!(System::events()
+					.iter()
+					.any(|record| record.event == Event::ParachainSystem(stale.clone())));
+			},
+		)
+		.add_with_post_test(
+			3,
+			|| {},
+			|| {
+				assert_eq!(ParachainSystem::stale_relay_parent_blocks(), 0);
+			},
+		);
+}
+
+#[test]
+fn stale_relay_parent_event_without_stale_blocks_allowed() {
+	set_max_stale_relay_parent_blocks(Some(0));
+
+	BlockTests::new().add_with_post_test(
+		1,
+		|| {},
+		|| {
+			// The first block is already the last one allowed on its relay parent.
+			let stale = crate::Event::<Test>::RelayParentStale(1);
+			assert!(System::events()
+				.iter()
+				.any(|record| record.event == Event::ParachainSystem(stale.clone())));
+		},
+	);
+}
+
+#[test]
+#[should_panic(expected = "Relay parent didn't advance for more than the allowed number of blocks")]
+fn no_stale_blocks_allowed() {
+	set_max_stale_relay_parent_blocks(Some(0));
+
+	BlockTests::new()
+		.with_validation_data(|_, _, data| {
+			data.relay_parent_number = 1;
+		})
+		.add(1, || {})
+		.add(2, || {});
+}
+
+#[test]
+#[should_panic(expected = "Relay parent didn't advance for more than the allowed number of blocks")]
+fn too_many_blocks_on_stale_relay_parent() {
+	BlockTests::new()
+		.with_validation_data(|_, _, data| {
+			data.relay_parent_number = 1;
+		})
+		.add(1, || {})
+		.add(2, || {})
+		.add(3, || {});
+}
+
+#[test]
+fn relay_parent_regression_is_allowed_with_event() {
+	BlockTests::new()
+		.with_validation_data(|_, block_number, data| {
+			// The relay chain reorgs to a shorter fork before block 2 is built.
+			data.relay_parent_number = if block_number == 2 { 0 } else { block_number };
+		})
+		.add(1, || {})
+		.add_with_post_test(
+			2,
+			|| {
+				assert_eq!(RelaychainBlockNumberProvider::<Test>::current_block_number(), 0);
+			},
+			|| {
+				let regressed = crate::Event::<Test>::RelayParentNumberRegressed(1, 0);
+				assert!(System::events()
+					.iter()
+					.any(|record| record.event == Event::ParachainSystem(regressed.clone())));
+				// The highest relay parent number is only tracked with `Clamp`.
+				assert!(HighestRelayChainBlockNumber::<Test>::get().is_none());
+			},
+		);
+}
+
+#[test]
+fn relay_parent_regression_is_clamped() {
+	set_relay_parent_regression_policy(RelayParentRegressionPolicy::Clamp);
+
+	BlockTests::new()
+		.with_validation_data(|_, block_number, data| {
+			// The relay chain reorgs to a shorter fork before block 3 is built.
+			data.relay_parent_number = if block_number == 3 { 1 } else { block_number * 10 };
+		})
+		.add(1, || {})
+		.add_with_post_test(
+			2,
+			|| {
+				assert_eq!(RelaychainBlockNumberProvider::<Test>::current_block_number(), 20);
+			},
+			|| {
+				assert_eq!(ParachainSystem::stale_relay_parent_blocks(), 0);
+			},
+		)
+		.add_with_post_test(
+			3,
+			|| {
+				assert_eq!(RelaychainBlockNumberProvider::<Test>::current_block_number(), 20);
+			},
+			|| {
+				// The block is built on a different relay parent, so it isn't stale.
+				assert_eq!(ParachainSystem::stale_relay_parent_blocks(), 0);
+				let regressed = crate::Event::<Test>::RelayParentNumberRegressed(20, 1);
+				assert!(System::events()
+					.iter()
+					.any(|record| record.event == Event::ParachainSystem(regressed.clone())));
+			},
+		)
+		.add(4, || {
+			assert_eq!(RelaychainBlockNumberProvider::<Test>::current_block_number(), 40);
+		});
+}
+
+#[test]
+fn clamped_relay_parent_regression_is_not_stale_while_the_fork_advances() {
+	set_relay_parent_regression_policy(RelayParentRegressionPolicy::Clamp);
+	set_max_stale_relay_parent_blocks(Some(1));
+
+	BlockTests::new()
+		.with_validation_data(|_, block_number, data| {
+			// Relay parents 10, 20, then a reorg onto a fork that advances 1, 2, 3, 4, 30.
+			data.relay_parent_number = match block_number {
+				1 => 10,
+				2 => 20,
+				7 => 30,
+				n => n - 2,
+			};
+		})
+		.add(1, || {})
+		.add(2, || {})
+		.add_with_post_test(
+			3,
+			|| {
+				assert_eq!(RelaychainBlockNumberProvider::<Test>::current_block_number(), 20);
+			},
+			|| {
+				assert_eq!(ParachainSystem::stale_relay_parent_blocks(), 0);
+			},
+		)
+		.add(4, || {
+			assert_eq!(RelaychainBlockNumberProvider::<Test>::current_block_number(), 20);
+		})
+		.add(5, || {
+			assert_eq!(RelaychainBlockNumberProvider::<Test>::current_block_number(), 20);
+		})
+		.add_with_post_test(
+			6,
+			|| {
+				assert_eq!(RelaychainBlockNumberProvider::<Test>::current_block_number(), 20);
+			},
+			|| {
+				assert_eq!(ParachainSystem::stale_relay_parent_blocks(), 0);
+				// The fork advancing after the reorg is not reported as a regression.
+				let regressions = System::events()
+					.iter()
+					.filter(|record| {
+						matches!(
+							record.event,
+							Event::ParachainSystem(crate::Event::RelayParentNumberRegressed(..))
+						)
+					})
+					.count();
+				assert_eq!(regressions, 0);
+			},
+		)
+		.add(7, || {
+			assert_eq!(RelaychainBlockNumberProvider::<Test>::current_block_number(), 30);
+		});
+}
+
+#[test]
+#[should_panic(expected = "Relay parent number is lower than the one of the previous block")]
+fn relay_parent_regression_is_rejected() {
+	set_relay_parent_regression_policy(RelayParentRegressionPolicy::Reject);
+
+	BlockTests::new()
+		.with_validation_data(|_, block_number, data| {
+			data.relay_parent_number = if block_number == 2 { 0 } else { block_number };
+		})
+		.add(1, || {})
+		.add(2, || {});
+}
+
+#[test]
+fn relay_state_proof_exposes_mocked_relay_state() {
+	use cumulus_primitives_parachain_inherent::{
+		MockRandomness, MockValidationDataInherentDataProvider,
+	};
+	use sp_inherents::InherentDataProvider;
+
+	BlockTests::new()
+		.with_inherent_data(|_, relay_block_number, data| {
+			let provider = MockValidationDataInherentDataProvider::new(0, 0, 0)
+				.with_relay_parent_number(relay_block_number)
+				.with_relay_state_config(MockRandomness::from_relay_parent_number(
+					RANDOMNESS_KEY.to_vec(),
+				));
+			let mut inherent_data = InherentData::default();
+			provider
+				.provide_inherent_data(&mut inherent_data)
+				.expect("provides inherent data");
+			*data = inherent_data
+				.get_data(&cumulus_primitives_parachain_inherent::INHERENT_IDENTIFIER)
+				.expect("inherent data decodes")
+				.expect("inherent data is present");
+		})
+		.add(1, || {
+			assert_eq!(relay_randomness(), Some(BlakeTwo256::hash_of(&1u32)));
+		})
+		.add(2, || {
+			assert_eq!(relay_randomness(), Some(BlakeTwo256::hash_of(&2u32)));
+		});
+}
+
+#[test]
+fn relay_state_proof_without_mocked_relay_state() {
+	BlockTests::new().add(1, || {
+		assert_eq!(relay_randomness(), None);
+	});
+}
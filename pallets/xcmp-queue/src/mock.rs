@@ -97,12 +97,15 @@ impl pallet_balances::Config for Test {
 impl cumulus_pallet_parachain_system::Config for Test {
 	type Event = Event;
 	type OnValidationData = ();
+	type OnRelayChainStateProof = ();
 	type SelfParaId = ();
 	type OutboundXcmpMessageSource = XcmpQueue;
 	type DmpMessageHandler = ();
 	type ReservedDmpWeight = ();
 	type XcmpMessageHandler = XcmpQueue;
 	type ReservedXcmpWeight = ();
+	type MaxStaleRelayParentBlocks = ();
+	type RelayParentRegressionPolicy = ();
 }
 
 parameter_types! {
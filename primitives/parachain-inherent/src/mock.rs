@@ -15,8 +15,10 @@
 // along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
 
 use crate::{ParachainInherentData, INHERENT_IDENTIFIER};
-use cumulus_primitives_core::PersistedValidationData;
+use codec::Encode;
+use cumulus_primitives_core::{relay_chain, PersistedValidationData};
 use sp_inherents::{InherentData, InherentDataProvider};
+use sp_runtime::traits::{BlakeTwo256, Hash};
 
 use cumulus_test_relay_sproof_builder::RelayStateSproofBuilder;
 
@@ -29,7 +31,15 @@ use cumulus_test_relay_sproof_builder::RelayStateSproofBuilder;
 /// relay_block_number = offset + relay_blocks_per_para_block * current_para_block
 /// To simulate a parachain that starts in relay block 1000 and gets a block in every other relay
 /// block, use 1000 and 2
-pub struct MockValidationDataInherentDataProvider {
+///
+/// Any other relay chain progression, e.g. a relay parent that doesn't advance or one that goes
+/// backwards because of a relay chain reorg, can be simulated with
+/// [`Self::with_relay_parent_number`] for the blocks in question.
+///
+/// Relay chain state that depends on the relay block, like randomness, can be mocked by passing
+/// something implementing [`MockRelayStateConfig`], e.g. [`MockRandomness`], to
+/// [`Self::with_relay_state_config`].
+pub struct MockValidationDataInherentDataProvider<R = ()> {
 	/// The current block number of the local block chain (the parachain)
 	pub current_para_block: u32,
 	/// The relay block in which this parachain appeared to start. This will be the relay block
@@ -38,21 +48,134 @@ pub struct MockValidationDataInherentDataProvider {
 	/// The number of relay blocks that elapses between each parablock. Probably set this to 1 or 2
 	/// to simulate optimistic or realistic relay chain behavior.
 	pub relay_blocks_per_para_block: u32,
+	/// The relay block number to use instead of the one calculated from `relay_offset` and
+	/// `relay_blocks_per_para_block`.
+	relay_parent_number_override: Option<relay_chain::BlockNumber>,
+	/// Additional relay chain state that is mocked for the relay block.
+	relay_state_config: R,
+}
+
+impl MockValidationDataInherentDataProvider {
+	/// Create a new instance that mocks no additional relay chain state.
+	pub fn new(
+		current_para_block: u32,
+		relay_offset: u32,
+		relay_blocks_per_para_block: u32,
+	) -> Self {
+		Self {
+			current_para_block,
+			relay_offset,
+			relay_blocks_per_para_block,
+			relay_parent_number_override: None,
+			relay_state_config: (),
+		}
+	}
+}
+
+impl<R> MockValidationDataInherentDataProvider<R> {
+	/// Mock the given additional relay chain state for the relay block.
+	pub fn with_relay_state_config<C>(
+		self,
+		relay_state_config: C,
+	) -> MockValidationDataInherentDataProvider<C> {
+		MockValidationDataInherentDataProvider {
+			current_para_block: self.current_para_block,
+			relay_offset: self.relay_offset,
+			relay_blocks_per_para_block: self.relay_blocks_per_para_block,
+			relay_parent_number_override: self.relay_parent_number_override,
+			relay_state_config,
+		}
+	}
+
+	/// Use the given relay block number instead of the one calculated from `relay_offset` and
+	/// `relay_blocks_per_para_block`.
+	pub fn with_relay_parent_number(
+		mut self,
+		relay_parent_number: relay_chain::BlockNumber,
+	) -> Self {
+		self.relay_parent_number_override = Some(relay_parent_number);
+		self
+	}
+
+	/// The mocked relay block number for the current para block.
+	pub fn relay_parent_number(&self) -> relay_chain::BlockNumber {
+		self.relay_parent_number_override.unwrap_or_else(|| {
+			self.relay_offset + self.relay_blocks_per_para_block * self.current_para_block
+		})
+	}
+}
+
+/// Something that mocks relay chain state at a given relay block.
+pub trait MockRelayStateConfig {
+	/// The relay chain storage entries to put into the relay chain state at the given relay block.
+	fn key_values(&self, relay_parent_number: relay_chain::BlockNumber) -> Vec<(Vec<u8>, Vec<u8>)>;
+}
+
+impl MockRelayStateConfig for () {
+	fn key_values(&self, _: relay_chain::BlockNumber) -> Vec<(Vec<u8>, Vec<u8>)> {
+		Vec::new()
+	}
+}
+
+impl<A: MockRelayStateConfig, B: MockRelayStateConfig> MockRelayStateConfig for (A, B) {
+	fn key_values(&self, relay_parent_number: relay_chain::BlockNumber) -> Vec<(Vec<u8>, Vec<u8>)> {
+		let mut key_values = self.0.key_values(relay_parent_number);
+		key_values.extend(self.1.key_values(relay_parent_number));
+		key_values
+	}
+}
+
+/// Mocks a randomness value stored under `key` in the relay chain state.
+///
+/// The randomness is computed by `generate` from the relay block number, so it changes as the
+/// mocked relay chain progresses.
+pub struct MockRandomness<F> {
+	/// The relay chain storage key the randomness is stored under.
+	pub key: Vec<u8>,
+	/// Computes the randomness for a given relay block number.
+	pub generate: F,
+}
+
+impl MockRandomness<fn(relay_chain::BlockNumber) -> relay_chain::Hash> {
+	/// Mocks randomness that is the hash of the relay block number.
+	///
+	/// Every relay block gets a different value and the same relay block always gets the same
+	/// value.
+	pub fn from_relay_parent_number(key: Vec<u8>) -> Self {
+		MockRandomness {
+			key,
+			generate: |relay_parent_number| BlakeTwo256::hash_of(&relay_parent_number),
+		}
+	}
+}
+
+impl<F> MockRelayStateConfig for MockRandomness<F>
+where
+	F: Fn(relay_chain::BlockNumber) -> relay_chain::Hash,
+{
+	fn key_values(&self, relay_parent_number: relay_chain::BlockNumber) -> Vec<(Vec<u8>, Vec<u8>)> {
+		vec![(self.key.clone(), (self.generate)(relay_parent_number).encode())]
+	}
 }
 
 #[async_trait::async_trait]
-impl InherentDataProvider for MockValidationDataInherentDataProvider {
+impl<R> InherentDataProvider for MockValidationDataInherentDataProvider<R>
+where
+	R: MockRelayStateConfig + Send + Sync,
+{
 	fn provide_inherent_data(
 		&self,
 		inherent_data: &mut InherentData,
 	) -> Result<(), sp_inherents::Error> {
-		// Use the "sproof" (spoof proof) builder to build valid mock state root and proof.
-		let (relay_storage_root, proof) =
-			RelayStateSproofBuilder::default().into_state_root_and_proof();
-
 		// Calculate the mocked relay block based on the current para block
-		let relay_parent_number =
-			self.relay_offset + self.relay_blocks_per_para_block * self.current_para_block;
+		let relay_parent_number = self.relay_parent_number();
+
+		// Use the "sproof" (spoof proof) builder to build valid mock state root and proof.
+		let sproof_builder = RelayStateSproofBuilder {
+			additional_key_values: self.relay_state_config.key_values(relay_parent_number),
+			..Default::default()
+		};
+		let (relay_storage_root, proof) = sproof_builder.into_state_root_and_proof();
 
 		let data = ParachainInherentData {
 			validation_data: PersistedValidationData {
@@ -78,3 +201,83 @@ impl InherentDataProvider for MockValidationDataInherentDataProvider {
 		None
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Decode;
+
+	const RANDOMNESS_KEY: &[u8] = b"mock_randomness";
+
+	fn provider<R>(
+		current_para_block: u32,
+		relay_state_config: R,
+	) -> MockValidationDataInherentDataProvider<R> {
+		MockValidationDataInherentDataProvider::new(current_para_block, 1000, 2)
+			.with_relay_state_config(relay_state_config)
+	}
+
+	fn inherent_data<R: MockRelayStateConfig + Send + Sync>(
+		provider: &MockValidationDataInherentDataProvider<R>,
+	) -> ParachainInherentData {
+		let mut inherent_data = InherentData::default();
+		provider
+			.provide_inherent_data(&mut inherent_data)
+			.expect("provides inherent data");
+		inherent_data
+			.get_data(&INHERENT_IDENTIFIER)
+			.expect("inherent data decodes")
+			.expect("inherent data is present")
+	}
+
+	fn read_randomness(data: ParachainInherentData) -> Option<relay_chain::Hash> {
+		let values = sp_state_machine::read_proof_check::<BlakeTwo256, _>(
+			data.validation_data.relay_parent_storage_root,
+			data.relay_chain_state,
+			vec![RANDOMNESS_KEY],
+		)
+		.expect("the proof matches the storage root");
+
+		values
+			.get(RANDOMNESS_KEY)
+			.cloned()
+			.flatten()
+			.map(|raw| relay_chain::Hash::decode(&mut &raw[..]).expect("randomness decodes"))
+	}
+
+	#[test]
+	fn mocked_randomness_changes_with_the_relay_block() {
+		let randomness = |current_para_block| {
+			let provider = provider(
+				current_para_block,
+				MockRandomness::from_relay_parent_number(RANDOMNESS_KEY.to_vec()),
+			);
+			read_randomness(inherent_data(&provider))
+		};
+
+		assert_eq!(randomness(1), Some(BlakeTwo256::hash_of(&1002u32)));
+		assert_eq!(randomness(2), Some(BlakeTwo256::hash_of(&1004u32)));
+		assert_ne!(randomness(1), randomness(2));
+	}
+
+	#[test]
+	fn relay_parent_number_can_be_overridden() {
+		let provider =
+			provider(1, MockRandomness::from_relay_parent_number(RANDOMNESS_KEY.to_vec()))
+				.with_relay_parent_number(7);
+
+		let data = inherent_data(&provider);
+		assert_eq!(data.validation_data.relay_parent_number, 7);
+		assert_eq!(read_randomness(data), Some(BlakeTwo256::hash_of(&7u32)));
+	}
+
+	#[test]
+	fn no_relay_state_config_produces_the_default_relay_state() {
+		let data = inherent_data(&provider(1, ()));
+		// The node order of proofs isn't deterministic, so only the roots are compared.
+		let (default_root, _) = RelayStateSproofBuilder::default().into_state_root_and_proof();
+
+		assert_eq!(data.validation_data.relay_parent_storage_root, default_root);
+		assert_eq!(read_randomness(data), None);
+	}
+}
@@ -42,6 +42,11 @@ pub struct RelayStateSproofBuilder {
 	pub hrmp_egress_channel_index: Option<Vec<ParaId>>,
 	pub hrmp_channels: BTreeMap<relay_chain::v1::HrmpChannelId, AbridgedHrmpChannel>,
 	pub current_slot: relay_chain::v1::Slot,
+	/// Additional key/value pairs that are put into the relay chain state.
+	///
+	/// This can be used to mock relay chain storage that isn't covered by the other fields, e.g.
+	/// randomness.
+	pub additional_key_values: Vec<(Vec<u8>, Vec<u8>)>,
 }
 
 impl Default for RelayStateSproofBuilder {
@@ -66,6 +71,7 @@ impl Default for RelayStateSproofBuilder {
 			hrmp_egress_channel_index: None,
 			hrmp_channels: BTreeMap::new(),
 			current_slot: 0.into(),
+			additional_key_values: Vec::new(),
 		}
 	}
 }
@@ -86,6 +92,7 @@ impl Clone for RelayStateSproofBuilder {
 			hrmp_egress_channel_index: self.hrmp_egress_channel_index.clone(),
 			hrmp_channels: self.hrmp_channels.clone(),
 			current_slot: self.current_slot.clone(),
+			additional_key_values: self.additional_key_values.clone(),
 		}
 	}
 }
@@ -173,6 +180,10 @@ impl RelayStateSproofBuilder {
 			}
 
 			insert(relay_chain::well_known_keys::CURRENT_SLOT.to_vec(), self.current_slot.encode());
+
+			for (key, value) in self.additional_key_values {
+				insert(key, value);
+			}
 		}
 
 		let root = backend.root().clone();